@@ -0,0 +1,116 @@
+//! Beam search over sampled rollouts — a middle ground between a cheap
+//! 1-ply greedy policy and full expectimax.
+//!
+//! Because the random tile spawn is stochastic, each expansion fixes a
+//! single sampled successor (one `add_random_tile` call per legal move)
+//! so the search tree for one planning step is deterministic.
+
+use crate::heuristic::{evaluate, HeuristicWeights};
+use crate::search::afterstate;
+use crate::{Action, State, Tile, ALL_ACTIONS};
+
+/// A beam node: the board, the first action taken to reach it from the
+/// root, and the cumulative in-game reward along that path.
+type BeamNode<T> = ([T; 16], Action, u64);
+
+/// Run `depth` rounds of beam search, keeping the top `beam_width` nodes
+/// by heuristic value plus accumulated reward, and return the first
+/// action of the best-scoring node. `depth == 0` is treated as a single
+/// 1-ply evaluation rather than a no-op, and `beam_width == 0` keeps a
+/// single node. Returns `None` only if `state`
+/// is already terminal (no legal moves).
+pub(crate) fn beam_search_action<T: Tile>(
+    state: &[T; 16],
+    beam_width: usize,
+    depth: u32,
+) -> Option<Action> {
+    let weights = HeuristicWeights::default();
+
+    let mut beam = expand(state, None, 0);
+    if beam.is_empty() {
+        return None;
+    }
+    keep_best(&mut beam, beam_width, &weights);
+
+    for _ in 1..depth.max(1) {
+        let mut children = Vec::new();
+        for (board, first_action, reward) in &beam {
+            children.extend(expand(board, Some(*first_action), *reward));
+        }
+        if children.is_empty() {
+            // Every beam node is a dead end; stick with the last full beam.
+            break;
+        }
+        beam = children;
+        keep_best(&mut beam, beam_width, &weights);
+    }
+
+    beam.into_iter()
+        .max_by(|a, b| score(a, &weights).partial_cmp(&score(b, &weights)).unwrap())
+        .map(|(_, first_action, _)| first_action)
+}
+
+/// Expand `board` by every legal action, sampling one random tile per
+/// child. `inherited_action` carries the root action forward once it is
+/// known; `cumulative_reward` is the path reward accrued so far.
+fn expand<T: Tile>(
+    board: &[T; 16],
+    inherited_action: Option<Action>,
+    cumulative_reward: u64,
+) -> Vec<BeamNode<T>> {
+    ALL_ACTIONS
+        .iter()
+        .filter_map(|action| {
+            let mut next = afterstate(board, action)?;
+            let move_reward = next.score() - board.score();
+            next.add_random_tile();
+            let first_action = inherited_action.unwrap_or(*action);
+            Some((next, first_action, cumulative_reward + move_reward))
+        })
+        .collect()
+}
+
+fn score<T: Tile>(node: &BeamNode<T>, weights: &HeuristicWeights) -> f64 {
+    evaluate(&node.0, weights) + node.2 as f64
+}
+
+fn keep_best<T: Tile>(beam: &mut Vec<BeamNode<T>>, beam_width: usize, weights: &HeuristicWeights) {
+    beam.sort_by(|a, b| score(b, weights).partial_cmp(&score(a, weights)).unwrap());
+    beam.truncate(beam_width.max(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beam_search_action_returns_none_on_a_dead_board() {
+        #[rustfmt::skip]
+        let state: [u8; 16] = [
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+        ];
+        assert_eq!(beam_search_action(&state, 4, 3), None);
+    }
+
+    #[test]
+    fn beam_search_action_treats_depth_zero_as_one_ply() {
+        // Depth 0 used to be treated as an already-terminal board, so it
+        // returned None even with legal moves available. It must still
+        // find one of them.
+        let state: [u8; 16] = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(beam_search_action(&state, 4, 0).is_some());
+    }
+
+    #[test]
+    fn expand_skips_illegal_actions() {
+        // Up is the only illegal move here: the two tiles already sit at
+        // the top of their columns, so sliding up changes nothing.
+        let state: [u8; 16] = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let children = expand(&state, None, 0);
+        assert_eq!(children.len(), ALL_ACTIONS.len() - 1);
+        assert!(children.iter().all(|(_, action, _)| *action != Action::Up));
+    }
+}
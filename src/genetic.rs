@@ -0,0 +1,200 @@
+//! A genetic tuner for [`HeuristicWeights`], evolving them via self-play
+//! with a 1-ply greedy policy driven by [`evaluate`].
+
+use crate::heuristic::{evaluate, HeuristicWeights};
+use crate::search::afterstate;
+use crate::{Action, State, ALL_ACTIONS};
+use rand::Rng;
+use std::f64::consts::PI;
+
+const ELITE_COUNT: usize = 2;
+const TOURNAMENT_SIZE: usize = 3;
+const MUTATION_RATE: f64 = 0.1;
+const MUTATION_SIGMA: f64 = 0.3;
+const WEIGHT_INIT_RANGE: f64 = 5.0;
+
+/// Evolve a population of `HeuristicWeights` for `generations` rounds,
+/// scoring each individual's fitness as its mean final `score()` over
+/// `games_per_eval` self-played games, and return the best weights seen.
+pub(crate) fn tune(population: usize, generations: u32, games_per_eval: u32) -> HeuristicWeights {
+    let mut rng = rand::thread_rng();
+    let mut pop: Vec<HeuristicWeights> = (0..population).map(|_| random_weights(&mut rng)).collect();
+
+    let mut best_overall = pop[0];
+    let mut best_overall_fitness = f64::NEG_INFINITY;
+
+    for generation in 0..generations {
+        let fitnesses: Vec<f64> = pop.iter().map(|w| fitness(w, games_per_eval)).collect();
+
+        let mut ranked: Vec<usize> = (0..pop.len()).collect();
+        ranked.sort_by(|&a, &b| fitnesses[b].partial_cmp(&fitnesses[a]).unwrap());
+
+        let best_fitness = fitnesses[ranked[0]];
+        let mean_fitness = fitnesses.iter().sum::<f64>() / fitnesses.len() as f64;
+        println!(
+            "generation {}: best fitness {:.1}, mean fitness {:.1}",
+            generation, best_fitness, mean_fitness
+        );
+
+        if best_fitness > best_overall_fitness {
+            best_overall_fitness = best_fitness;
+            best_overall = pop[ranked[0]];
+        }
+
+        let mut next_gen: Vec<HeuristicWeights> =
+            ranked.iter().take(ELITE_COUNT).map(|&i| pop[i]).collect();
+        while next_gen.len() < population {
+            let parent_a = tournament_select(&pop, &fitnesses, &mut rng);
+            let parent_b = tournament_select(&pop, &fitnesses, &mut rng);
+            let mut child = crossover(&parent_a, &parent_b, &mut rng);
+            mutate(&mut child, &mut rng);
+            next_gen.push(child);
+        }
+        pop = next_gen;
+    }
+
+    best_overall
+}
+
+fn random_weights(rng: &mut impl Rng) -> HeuristicWeights {
+    HeuristicWeights::new(
+        rng.gen_range(0.0, WEIGHT_INIT_RANGE),
+        rng.gen_range(0.0, WEIGHT_INIT_RANGE),
+        rng.gen_range(0.0, WEIGHT_INIT_RANGE),
+        rng.gen_range(0.0, WEIGHT_INIT_RANGE),
+    )
+}
+
+fn tournament_select(
+    pop: &[HeuristicWeights],
+    fitnesses: &[f64],
+    rng: &mut impl Rng,
+) -> HeuristicWeights {
+    let winner = (0..TOURNAMENT_SIZE)
+        .map(|_| rng.gen_range(0, pop.len()))
+        .max_by(|&a, &b| fitnesses[a].partial_cmp(&fitnesses[b]).unwrap())
+        .unwrap();
+    pop[winner]
+}
+
+fn crossover(a: &HeuristicWeights, b: &HeuristicWeights, rng: &mut impl Rng) -> HeuristicWeights {
+    HeuristicWeights::new(
+        if rng.gen_bool(0.5) { a.empty_weight } else { b.empty_weight },
+        if rng.gen_bool(0.5) {
+            a.monotonicity_weight
+        } else {
+            b.monotonicity_weight
+        },
+        if rng.gen_bool(0.5) {
+            a.smoothness_weight
+        } else {
+            b.smoothness_weight
+        },
+        if rng.gen_bool(0.5) { a.corner_weight } else { b.corner_weight },
+    )
+}
+
+fn mutate(weights: &mut HeuristicWeights, rng: &mut impl Rng) {
+    if rng.gen_bool(MUTATION_RATE) {
+        weights.empty_weight += sample_gaussian(rng);
+    }
+    if rng.gen_bool(MUTATION_RATE) {
+        weights.monotonicity_weight += sample_gaussian(rng);
+    }
+    if rng.gen_bool(MUTATION_RATE) {
+        weights.smoothness_weight += sample_gaussian(rng);
+    }
+    if rng.gen_bool(MUTATION_RATE) {
+        weights.corner_weight += sample_gaussian(rng);
+    }
+}
+
+/// A standard normal sample via the Box-Muller transform, scaled by
+/// `MUTATION_SIGMA`.
+fn sample_gaussian(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::MIN_POSITIVE, 1.0);
+    let u2: f64 = rng.gen_range(0.0, 1.0);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos() * MUTATION_SIGMA
+}
+
+/// Play one game to completion with the 1-ply greedy policy driven by
+/// `evaluate`, reusing the existing `advance_state`/`score` machinery.
+fn play_game(weights: &HeuristicWeights) -> u64 {
+    let mut state: [u8; 16] = State::new();
+    loop {
+        match greedy_action(&state, weights) {
+            Some(action) => {
+                state.advance_state(&action);
+            }
+            None => break,
+        }
+    }
+    state.score()
+}
+
+fn greedy_action(state: &[u8; 16], weights: &HeuristicWeights) -> Option<Action> {
+    ALL_ACTIONS
+        .iter()
+        .filter_map(|action| {
+            let after = afterstate(state, action)?;
+            Some((*action, evaluate(&after, weights)))
+        })
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(action, _)| action)
+}
+
+fn fitness(weights: &HeuristicWeights, games_per_eval: u32) -> f64 {
+    let total: u64 = (0..games_per_eval).map(|_| play_game(weights)).sum();
+    total as f64 / games_per_eval as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tournament_select_with_a_single_candidate_returns_it() {
+        let pop = [HeuristicWeights::new(1.0, 2.0, 3.0, 4.0)];
+        let fitnesses = [10.0];
+        let mut rng = rand::thread_rng();
+        assert_eq!(tournament_select(&pop, &fitnesses, &mut rng), pop[0]);
+    }
+
+    #[test]
+    fn crossover_picks_each_field_from_one_parent_or_the_other() {
+        let a = HeuristicWeights::new(1.0, 2.0, 3.0, 4.0);
+        let b = HeuristicWeights::new(5.0, 6.0, 7.0, 8.0);
+        let mut rng = rand::thread_rng();
+
+        let child = crossover(&a, &b, &mut rng);
+
+        assert!(child.empty_weight == a.empty_weight || child.empty_weight == b.empty_weight);
+        assert!(
+            child.monotonicity_weight == a.monotonicity_weight
+                || child.monotonicity_weight == b.monotonicity_weight
+        );
+        assert!(
+            child.smoothness_weight == a.smoothness_weight
+                || child.smoothness_weight == b.smoothness_weight
+        );
+        assert!(child.corner_weight == a.corner_weight || child.corner_weight == b.corner_weight);
+    }
+
+    #[test]
+    fn greedy_action_returns_none_on_a_dead_board() {
+        #[rustfmt::skip]
+        let state: [u8; 16] = [
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+        ];
+        assert_eq!(greedy_action(&state, &HeuristicWeights::default()), None);
+    }
+
+    #[test]
+    fn greedy_action_finds_a_move_when_one_exists() {
+        let state: [u8; 16] = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(greedy_action(&state, &HeuristicWeights::default()).is_some());
+    }
+}
@@ -0,0 +1,163 @@
+//! A configurable heuristic for scoring a board position, shared by the
+//! search agents and by the genetic tuner that evolves its weights.
+
+use crate::Tile;
+
+/// Tunable weights for [`evaluate`]. The defaults favour boards with the
+/// largest tile pinned to a corner, since that's the position from which
+/// the most merges stay available.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct HeuristicWeights {
+    pub(crate) empty_weight: f64,
+    pub(crate) monotonicity_weight: f64,
+    pub(crate) smoothness_weight: f64,
+    pub(crate) corner_weight: f64,
+}
+
+impl HeuristicWeights {
+    pub(crate) fn new(
+        empty_weight: f64,
+        monotonicity_weight: f64,
+        smoothness_weight: f64,
+        corner_weight: f64,
+    ) -> Self {
+        HeuristicWeights {
+            empty_weight,
+            monotonicity_weight,
+            smoothness_weight,
+            corner_weight,
+        }
+    }
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights::new(2.7, 1.0, 0.1, 1.0)
+    }
+}
+
+/// Score `state` from the raw exponent array: more empty cells, rows and
+/// columns that sort consistently in one direction, fewer sharp jumps
+/// between neighbouring tiles, and the largest tile sitting in a corner
+/// are all rewarded.
+pub(crate) fn evaluate<T: Tile>(state: &[T; 16], w: &HeuristicWeights) -> f64 {
+    w.empty_weight * count_empty(state) as f64 + w.monotonicity_weight * monotonicity(state)
+        - w.smoothness_weight * smoothness(state)
+        + w.corner_weight * corner_bonus(state)
+}
+
+fn count_empty<T: Tile>(state: &[T; 16]) -> usize {
+    state.iter().filter(|tile| tile.is_zero()).count()
+}
+
+/// For rows and, separately, columns: sum the increasing-direction and
+/// decreasing-direction differences between neighbours, keep whichever
+/// direction fits better (the smaller sum), and return the negation so a
+/// perfectly sorted board scores close to 0 and a jumbled one scores very
+/// negative.
+fn monotonicity<T: Tile>(state: &[T; 16]) -> f64 {
+    let value = |i: usize| state[i].to_f64().unwrap();
+
+    let mut row_increasing = 0.0;
+    let mut row_decreasing = 0.0;
+    for row in 0..4 {
+        for col in 0..3 {
+            let a = value(row * 4 + col);
+            let b = value(row * 4 + col + 1);
+            if a > b {
+                row_decreasing += a - b;
+            } else {
+                row_increasing += b - a;
+            }
+        }
+    }
+
+    let mut col_increasing = 0.0;
+    let mut col_decreasing = 0.0;
+    for col in 0..4 {
+        for row in 0..3 {
+            let a = value(row * 4 + col);
+            let b = value((row + 1) * 4 + col);
+            if a > b {
+                col_decreasing += a - b;
+            } else {
+                col_increasing += b - a;
+            }
+        }
+    }
+
+    -(row_increasing.min(row_decreasing) + col_increasing.min(col_decreasing))
+}
+
+/// Sum of the absolute exponent difference between every pair of
+/// horizontally or vertically adjacent occupied tiles.
+fn smoothness<T: Tile>(state: &[T; 16]) -> f64 {
+    let mut penalty = 0.0;
+    for row in 0..4 {
+        for col in 0..3 {
+            let a = state[row * 4 + col];
+            let b = state[row * 4 + col + 1];
+            if !a.is_zero() && !b.is_zero() {
+                penalty += (a.to_f64().unwrap() - b.to_f64().unwrap()).abs();
+            }
+        }
+    }
+    for col in 0..4 {
+        for row in 0..3 {
+            let a = state[row * 4 + col];
+            let b = state[(row + 1) * 4 + col];
+            if !a.is_zero() && !b.is_zero() {
+                penalty += (a.to_f64().unwrap() - b.to_f64().unwrap()).abs();
+            }
+        }
+    }
+    penalty
+}
+
+/// The exponent of the largest tile, if it sits in one of the four corners,
+/// else 0.
+fn corner_bonus<T: Tile>(state: &[T; 16]) -> f64 {
+    const CORNERS: [usize; 4] = [0, 3, 12, 15];
+    let max_value = state
+        .iter()
+        .map(|tile| tile.to_f64().unwrap())
+        .fold(0.0, f64::max);
+    if max_value > 0.0 && CORNERS.iter().any(|&i| state[i].to_f64().unwrap() == max_value) {
+        max_value
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_board_scores_only_from_empty_weight() {
+        let state = [0u8; 16];
+        let weights = HeuristicWeights::new(1.0, 0.0, 0.0, 0.0);
+        assert_eq!(evaluate(&state, &weights), 16.0);
+    }
+
+    #[test]
+    fn corner_bonus_rewards_the_largest_tile_sitting_in_a_corner() {
+        let mut cornered = [0u8; 16];
+        cornered[0] = 10;
+        let mut centered = [0u8; 16];
+        centered[5] = 10;
+        let weights = HeuristicWeights::new(0.0, 0.0, 0.0, 1.0);
+
+        assert_eq!(evaluate(&cornered, &weights), 10.0);
+        assert_eq!(evaluate(&centered, &weights), 0.0);
+    }
+
+    #[test]
+    fn monotonicity_prefers_a_sorted_row_over_a_jumbled_one() {
+        let sorted: [u8; 16] = [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let jumbled: [u8; 16] = [4, 1, 3, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let weights = HeuristicWeights::new(0.0, 1.0, 0.0, 0.0);
+
+        assert!(evaluate(&sorted, &weights) > evaluate(&jumbled, &weights));
+    }
+}
@@ -0,0 +1,222 @@
+//! N-tuple network value function trained with afterstate TD(0).
+//!
+//! The network is a handful of small lookup tables ("tuples"), each
+//! indexed by the exponents of a few board cells; the board's value is
+//! the sum of every tuple's lookup. Each physical tuple is evaluated
+//! under all 8 symmetries of the square board (4 rotations times a
+//! horizontal flip) and shares one table across them, so training on one
+//! orientation generalizes to the rest.
+
+use crate::search::afterstate;
+use crate::{Action, State, Tile, ALL_ACTIONS};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// Tile exponents are clamped into `[0, NUM_VALUES)` before indexing a
+/// table; 16 comfortably covers every exponent seen in practice.
+const NUM_VALUES: usize = 16;
+
+/// The physical tuple shapes. Two horizontal 4-cell lines are enough: the
+/// 8-fold symmetry of the top row's orbit already covers the bottom row and
+/// both edge columns, and the second row's orbit covers the inner row and
+/// both inner columns.
+const TUPLES: [[usize; 4]; 2] = [[0, 1, 2, 3], [4, 5, 6, 7]];
+
+fn table_size() -> usize {
+    NUM_VALUES.pow(4)
+}
+
+/// One of the 8 symmetries of a square: 4 rotations, each optionally
+/// mirrored horizontally.
+fn transform_coords(symmetry: usize, row: usize, col: usize) -> (usize, usize) {
+    let (row, col) = match symmetry % 4 {
+        0 => (row, col),
+        1 => (col, 3 - row),
+        2 => (3 - row, 3 - col),
+        _ => (3 - col, row),
+    };
+    if symmetry >= 4 {
+        (row, 3 - col)
+    } else {
+        (row, col)
+    }
+}
+
+fn apply_symmetry<T: Tile>(state: &[T; 16], symmetry: usize) -> [T; 16] {
+    let mut out = [T::default(); 16];
+    for (position, slot) in out.iter_mut().enumerate() {
+        let (row, col) = transform_coords(symmetry, position / 4, position % 4);
+        *slot = state[row * 4 + col];
+    }
+    out
+}
+
+fn tuple_index<T: Tile>(state: &[T; 16], tuple: &[usize; 4]) -> usize {
+    tuple.iter().fold(0, |index, &cell| {
+        let exponent = state[cell].to_usize().unwrap().min(NUM_VALUES - 1);
+        index * NUM_VALUES + exponent
+    })
+}
+
+/// A trained (or freshly initialized) n-tuple value function.
+pub(crate) struct NTupleNetwork {
+    tables: Vec<Vec<f64>>,
+}
+
+impl NTupleNetwork {
+    pub(crate) fn new() -> Self {
+        NTupleNetwork {
+            tables: TUPLES.iter().map(|_| vec![0.0; table_size()]).collect(),
+        }
+    }
+
+    /// `V(state)`: sum of every tuple's table entry, over every symmetric
+    /// placement of that tuple.
+    pub(crate) fn value<T: Tile>(&self, state: &[T; 16]) -> f64 {
+        let mut total = 0.0;
+        for (tuple, table) in TUPLES.iter().zip(&self.tables) {
+            for symmetry in 0..8 {
+                let symmetrized = apply_symmetry(state, symmetry);
+                total += table[tuple_index(&symmetrized, tuple)];
+            }
+        }
+        total
+    }
+
+    /// Add `error` to `state`'s contributing table entries, split equally
+    /// across every tuple and every symmetric placement.
+    fn update<T: Tile>(&mut self, state: &[T; 16], error: f64) {
+        let share = error / (TUPLES.len() * 8) as f64;
+        for (tuple, table) in TUPLES.iter().zip(&mut self.tables) {
+            for symmetry in 0..8 {
+                let symmetrized = apply_symmetry(state, symmetry);
+                table[tuple_index(&symmetrized, tuple)] += share;
+            }
+        }
+    }
+
+    /// The legal action maximizing `reward + V(afterstate)`.
+    pub(crate) fn action_from_value<T: Tile>(&self, state: &[T; 16]) -> Option<Action> {
+        ALL_ACTIONS
+            .iter()
+            .filter_map(|action| {
+                let after = afterstate(state, action)?;
+                let reward = (after.score() - state.score()) as f64;
+                Some((*action, reward + self.value(&after)))
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(action, _)| action)
+    }
+
+    /// Play `num_games` self-play games, updating the network after every
+    /// move via afterstate TD(0): `V(s') += alpha * (r_next + V(s'') - V(s'))`.
+    pub(crate) fn train(&mut self, num_games: u32, alpha: f64) {
+        for _ in 0..num_games {
+            let mut state: [u8; 16] = State::new();
+            let mut prev_afterstate: Option<[u8; 16]> = None;
+            loop {
+                let action = match self.action_from_value(&state) {
+                    Some(action) => action,
+                    None => break,
+                };
+                let after =
+                    afterstate(&state, &action).expect("greedy action is always legal");
+                let reward = (after.score() - state.score()) as f64;
+                if let Some(prev) = prev_afterstate {
+                    let td_error = reward + self.value(&after) - self.value(&prev);
+                    self.update(&prev, alpha * td_error);
+                }
+                prev_afterstate = Some(after);
+                state = after;
+                state.add_random_tile();
+            }
+            // The game ended right after `prev_afterstate`; there is no
+            // further reward or next afterstate, so its target value is 0.
+            if let Some(prev) = prev_afterstate {
+                let td_error = -self.value(&prev);
+                self.update(&prev, alpha * td_error);
+            }
+        }
+    }
+
+    pub(crate) fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for table in &self.tables {
+            for value in table {
+                writeln!(writer, "{}", value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load(path: &str) -> io::Result<Self> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let mut tables = Vec::with_capacity(TUPLES.len());
+        for _ in 0..TUPLES.len() {
+            let mut table = Vec::with_capacity(table_size());
+            for _ in 0..table_size() {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated n-tuple table file"))??;
+                table.push(
+                    line.parse::<f64>()
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                );
+            }
+            tables.push(table);
+        }
+        Ok(NTupleNetwork { tables })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_coords_identity_symmetry_is_a_no_op() {
+        for row in 0..4 {
+            for col in 0..4 {
+                assert_eq!(transform_coords(0, row, col), (row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn fresh_network_values_every_board_at_zero() {
+        let net = NTupleNetwork::new();
+        let state: [u8; 16] = [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(net.value(&state), 0.0);
+    }
+
+    #[test]
+    fn update_then_value_round_trips_the_full_error() {
+        let mut net = NTupleNetwork::new();
+        // All 16 cells distinct so none of the 8 symmetries, across either
+        // tuple, collide on the same table entry; every `+= share` lands
+        // on its own cell and the full error reads back exactly.
+        let state: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        net.update(&state, 8.0);
+        assert!((net.value(&state) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn action_from_value_returns_none_on_a_board_with_no_legal_moves() {
+        #[rustfmt::skip]
+        let state: [u8; 16] = [
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+        ];
+        let net = NTupleNetwork::new();
+        assert_eq!(net.action_from_value(&state), None);
+    }
+
+    #[test]
+    fn action_from_value_finds_a_legal_move_when_one_exists() {
+        let state: [u8; 16] = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let net = NTupleNetwork::new();
+        assert!(net.action_from_value(&state).is_some());
+    }
+}
@@ -1,4 +1,10 @@
-use itertools::Itertools;
+mod beam;
+mod bitboard;
+mod genetic;
+mod heuristic;
+mod learning;
+mod search;
+
 use num::{One, ToPrimitive, Zero};
 use pyo3::prelude::*;
 use rand::seq::SliceRandom;
@@ -8,6 +14,7 @@ use std::convert::TryInto;
 use std::fmt;
 use std::hash::Hash;
 use std::ops::AddAssign;
+use std::sync::{Mutex, OnceLock};
 
 // rust-cpython aware function. All of our python interface could be
 // declared in a separate module.
@@ -21,22 +28,20 @@ fn r2048(_py: Python, m: &PyModule) -> PyResult<()> {
         Ok(Box::new(board).to_vec())
     }
 
+    #[pyfn(m, "reset")]
+    fn reset_py() -> PyResult<Vec<usize>> {
+        new_248_py()
+    }
+
     #[pyfn(m, "step")]
     fn step_2048_py(state: Vec<usize>, action: usize) -> PyResult<(Vec<usize>, u64, bool)> {
-        let boxed_slice = state.into_boxed_slice();
-        let boxed_array: Box<[usize; 16]> = match boxed_slice.try_into() {
-            Ok(ba) => ba,
-            Err(o) => panic!("Expected a Vec of length {} but it was {}", 16, o.len()),
-        };
-        let mut input_state = *boxed_array;
-        let old_state = input_state;
-        let act = match action {
-            0 => Some(Action::Down),
-            1 => Some(Action::Left),
-            2 => Some(Action::Right),
-            3 => Some(Action::Up),
-            _ => None,
-        };
+        let board = unpack_board(state);
+        // Packed into the bitboard backend for the advance itself: it's an
+        // order of magnitude cheaper than the `[T; 16]` slide/merge, and
+        // `State` is the only interface this function needs from it.
+        let mut input_state = bitboard::BitState::from_array(&board);
+        let old_score = input_state.score();
+        let act = action_from_index(action);
         let mut done = false;
         match act {
             Some(a) => {
@@ -46,21 +51,166 @@ fn r2048(_py: Python, m: &PyModule) -> PyResult<()> {
                 panic!("Action outside of [0,1,2,3]",);
             }
         };
-        let reward = input_state.score() - old_state.score();
-        Ok((Box::new(input_state).to_vec(), reward, done))
+        let reward = input_state.score() - old_score;
+        Ok((input_state.to_array().to_vec(), reward, done))
+    }
+
+    #[pyfn(m, "best_action")]
+    fn best_action_py(state: Vec<usize>, depth: u32) -> PyResult<Option<usize>> {
+        let board = unpack_board(state);
+        Ok(search::best_action(&board, depth).map(index_from_action))
+    }
+
+    #[pyfn(m, "beam_search_action")]
+    fn beam_search_action_py(
+        state: Vec<usize>,
+        beam_width: usize,
+        depth: u32,
+    ) -> PyResult<Option<usize>> {
+        let board = unpack_board(state);
+        Ok(beam::beam_search_action(&board, beam_width, depth).map(index_from_action))
+    }
+
+    #[pyfn(m, "train_n_tuple_network")]
+    fn train_n_tuple_network_py(num_games: u32, alpha: f64, path: String) -> PyResult<()> {
+        let mut network = learning::NTupleNetwork::new();
+        network.train(num_games, alpha);
+        network
+            .save(&path)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    #[pyfn(m, "n_tuple_action")]
+    fn n_tuple_action_py(state: Vec<usize>, path: String) -> PyResult<Option<usize>> {
+        let board = unpack_board(state);
+        let mut cache = n_tuple_network_cache()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !matches!(cache.as_ref(), Some((cached_path, _)) if *cached_path == path) {
+            let network = learning::NTupleNetwork::load(&path)
+                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+            *cache = Some((path, network));
+        }
+        let network = &cache.as_ref().unwrap().1;
+        Ok(network.action_from_value(&board).map(index_from_action))
+    }
+
+    #[pyfn(m, "peek")]
+    fn peek_py(state: Vec<usize>, action: usize) -> PyResult<(Vec<usize>, u64, bool)> {
+        let board = unpack_board(state);
+        let act = match action_from_index(action) {
+            Some(a) => a,
+            None => panic!("Action outside of [0,1,2,3]",),
+        };
+        match search::afterstate(&board, &act) {
+            Some(after) => {
+                let reward = after.score() - board.score();
+                Ok((Box::new(after).to_vec(), reward, true))
+            }
+            None => Ok((Box::new(board).to_vec(), 0, false)),
+        }
+    }
+
+    #[pyfn(m, "legal_actions")]
+    fn legal_actions_py(state: Vec<usize>) -> PyResult<Vec<usize>> {
+        let board = unpack_board(state);
+        Ok(ALL_ACTIONS
+            .iter()
+            .filter(|action| search::afterstate(&board, action).is_some())
+            .map(|action| index_from_action(*action))
+            .collect())
+    }
+
+    #[pyfn(m, "render")]
+    fn render_py(state: Vec<usize>) -> PyResult<String> {
+        let board = unpack_board(state);
+        let board: &dyn State<usize> = &board;
+        Ok(format!("{}", board))
+    }
+
+    #[pyfn(m, "tune_heuristic_weights")]
+    fn tune_heuristic_weights_py(
+        population: usize,
+        generations: u32,
+        games_per_eval: u32,
+    ) -> PyResult<(f64, f64, f64, f64)> {
+        if population == 0 {
+            panic!("population must be at least 1");
+        }
+        if games_per_eval == 0 {
+            panic!("games_per_eval must be at least 1");
+        }
+        let weights = genetic::tune(population, generations, games_per_eval);
+        Ok((
+            weights.empty_weight,
+            weights.monotonicity_weight,
+            weights.smoothness_weight,
+            weights.corner_weight,
+        ))
     }
 
     Ok(())
 }
 
-#[derive(Copy, Clone)]
-enum Action {
+/// The most recently loaded n-tuple table, keyed by its source path, so
+/// `n_tuple_action` can be called once per move without re-reading and
+/// re-parsing the table file on every call.
+fn n_tuple_network_cache() -> &'static Mutex<Option<(String, learning::NTupleNetwork)>> {
+    static CACHE: OnceLock<Mutex<Option<(String, learning::NTupleNetwork)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// Unpack the flat board every pyfn receives across the FFI boundary into
+/// the fixed-size array the backends and search/learning code operate on.
+fn unpack_board(state: Vec<usize>) -> [usize; 16] {
+    let boxed_slice = state.into_boxed_slice();
+    let boxed_array: Box<[usize; 16]> = match boxed_slice.try_into() {
+        Ok(ba) => ba,
+        Err(o) => panic!("Expected a Vec of length {} but it was {}", 16, o.len()),
+    };
+    *boxed_array
+}
+
+fn action_from_index(index: usize) -> Option<Action> {
+    match index {
+        0 => Some(Action::Down),
+        1 => Some(Action::Left),
+        2 => Some(Action::Right),
+        3 => Some(Action::Up),
+        _ => None,
+    }
+}
+
+fn index_from_action(action: Action) -> usize {
+    match action {
+        Action::Down => 0,
+        Action::Left => 1,
+        Action::Right => 2,
+        Action::Up => 3,
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Action {
     Up,
     Down,
     Left,
     Right,
 }
 
+pub(crate) const ALL_ACTIONS: [Action; 4] = [Action::Up, Action::Down, Action::Left, Action::Right];
+
+/// The bound shared by every tile/board representation in the crate: the
+/// set of operations the `[T; 16]` `State` impl and the search/learning
+/// modules built on top of it need from a tile's exponent type.
+pub(crate) trait Tile:
+    Default + One + Zero + ToPrimitive + Eq + Hash + AddAssign + Copy + ToString
+{
+}
+
+impl<T> Tile for T where T: Default + One + Zero + ToPrimitive + Eq + Hash + AddAssign + Copy + ToString
+{}
+
 trait State<T> {
     fn new() -> Self
     where
@@ -87,16 +237,21 @@ trait State<T> {
 
 impl<T> fmt::Display for dyn State<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let string: String = self
-            .to_string()
-            .as_str()
-            .chars()
-            .interleave("   \n   \n   \n   ".chars())
-            .collect();
-        write!(f, "{}", string)
+        write!(f, "{}", self.to_string())
     }
 }
 
+/// Render 16 cell strings (row-major) as a 4x4 grid, right-aligning each
+/// cell to a fixed width so multi-digit tile exponents (anything 1024 and
+/// up) still line up instead of running into their neighbours.
+pub(crate) fn render_grid(cells: &[String; 16]) -> String {
+    cells
+        .chunks(4)
+        .map(|row| row.iter().map(|cell| format!("{:>4}", cell)).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 impl<
         T: Default + One + Zero + ToPrimitive + Eq + Hash + AddAssign + Copy + ToString + Copy + Clone,
     > State<T> for [T; 16]
@@ -153,7 +308,8 @@ impl<
     }
 
     fn to_string(&self) -> String {
-        self.iter().map(|x| x.to_string()).collect()
+        let cells: Vec<String> = self.iter().map(|x| x.to_string()).collect();
+        render_grid(&cells.try_into().expect("16 cells"))
     }
 
     fn score(&self) -> u64 {
@@ -318,24 +474,60 @@ fn main() {
     let moves: Vec<Action> = (0..100000000)
         .map(|_x| *move_choices.choose(&mut rng).unwrap())
         .collect();
-    let mut steps = 0;
-    let now = std::time::Instant::now();
 
-    let mut total_games = 0;
+    let (games, steps, elapsed) = run_benchmark::<[u8; 16]>(&moves);
+    println!(
+        "[u8; 16]   : {} boards over {} moves averaging {} moves per board in {:?}",
+        games,
+        steps,
+        steps / games,
+        elapsed
+    );
+
+    let (games, steps, elapsed) = run_benchmark::<bitboard::BitState>(&moves);
+    println!(
+        "BitState   : {} boards over {} moves averaging {} moves per board in {:?}",
+        games,
+        steps,
+        steps / games,
+        elapsed
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1024 tile (exponent 10) is common in any real game; rendering one
+    // used to silently truncate the board because the grid was built by
+    // interleaving raw digit characters, which assumed one digit per cell.
+    #[test]
+    fn render_handles_two_digit_exponents_without_truncation() {
+        let board: [usize; 16] = [0, 1, 2, 10, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let board: &dyn State<usize> = &board;
+        let rendered = format!("{}", board);
+        let rows: Vec<&str> = rendered.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], "   0   1   2  10");
+        assert_eq!(rows[1], "  11   0   0   0");
+    }
+}
+
+/// Play `moves` to completion across a million fresh boards of backend `S`,
+/// restarting a board whenever it reaches game over. Returns
+/// `(games, steps, elapsed)` so `main` can compare backends on equal terms.
+fn run_benchmark<S: State<u8>>(moves: &[Action]) -> (u64, u64, std::time::Duration) {
+    let now = std::time::Instant::now();
+    let mut steps = 0u64;
+    let mut total_games = 0u64;
     for _ in 0..1000000 {
-        let mut test: [u8; 16] = State::new();
+        let mut test: S = State::new();
         total_games += 1;
         let mut is_game_over = false;
         while !is_game_over {
-            is_game_over = test.advance_state(&moves[steps]);
+            is_game_over = test.advance_state(&moves[steps as usize]);
             steps += 1;
         }
     }
-    println!(
-        "{} boards over {} moves averaging {} moves per board",
-        total_games,
-        steps,
-        steps / total_games
-    );
-    println!("{:?}", now.elapsed());
+    (total_games, steps, now.elapsed())
 }
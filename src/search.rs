@@ -0,0 +1,159 @@
+//! Expectimax search over the `[T; 16]` board representation.
+//!
+//! 2048's random tile spawn makes the game a max/chance alternation
+//! rather than pure minimax: the player picks an `Action` (the max
+//! layer), and then the environment places a random tile in one of the
+//! empty cells (the chance layer). `best_action` searches this tree to a
+//! fixed depth, falling back to a heuristic evaluation at the leaves.
+
+use crate::heuristic::{evaluate, HeuristicWeights};
+use crate::{Action, State, Tile, ALL_ACTIONS};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Above this many empty cells, the chance layer samples a subset of them
+/// instead of enumerating every possibility, to keep the branching factor
+/// bounded on an open board.
+const CHANCE_SAMPLE_THRESHOLD: usize = 6;
+
+/// Search `depth` plies ahead with expectimax and return the best legal
+/// `Action`, or `None` if the board has no legal moves.
+pub(crate) fn best_action<T: Tile>(state: &[T; 16], depth: u32) -> Option<Action> {
+    let weights = HeuristicWeights::default();
+    let mut rng = rand::thread_rng();
+    let mut best: Option<(Action, f64)> = None;
+    for action in ALL_ACTIONS.iter() {
+        if let Some(after) = afterstate(state, action) {
+            let value = chance_value(&after, depth, &weights, &mut rng);
+            if best.is_none() || value > best.unwrap().1 {
+                best = Some((*action, value));
+            }
+        }
+    }
+    best.map(|(action, _)| action)
+}
+
+/// Apply `action`'s slide+merge sequence without placing a random tile,
+/// returning `None` if the board is unchanged (an illegal move).
+pub(crate) fn afterstate<T: Tile>(state: &[T; 16], action: &Action) -> Option<[T; 16]> {
+    let mut board = *state;
+    match action {
+        Action::Up => {
+            board.slide_up();
+            board.merge_up();
+            board.slide_up();
+        }
+        Action::Down => {
+            board.slide_down();
+            board.merge_down();
+            board.slide_down();
+        }
+        Action::Left => {
+            board.slide_left();
+            board.merge_left();
+            board.slide_left();
+        }
+        Action::Right => {
+            board.slide_right();
+            board.merge_right();
+            board.slide_right();
+        }
+    }
+    if board == *state {
+        None
+    } else {
+        Some(board)
+    }
+}
+
+/// The max layer: try every action from `state` and keep the best
+/// expected value, or fall back to the heuristic at the depth limit or a
+/// dead board.
+fn max_value<T: Tile>(state: &[T; 16], depth: u32, weights: &HeuristicWeights, rng: &mut impl Rng) -> f64 {
+    if depth == 0 {
+        return evaluate(state, weights);
+    }
+    let mut best: Option<f64> = None;
+    for action in ALL_ACTIONS.iter() {
+        if let Some(after) = afterstate(state, action) {
+            let value = chance_value(&after, depth, weights, rng);
+            best = Some(best.map_or(value, |b: f64| b.max(value)));
+        }
+    }
+    best.unwrap_or_else(|| evaluate(state, weights))
+}
+
+/// The chance layer: weight each empty cell by `1 / num_empty` (or
+/// `1 / sample size` when sampling) and each spawned tile by its spawn
+/// probability, matching `State::add_random_tile`.
+fn chance_value<T: Tile>(
+    board: &[T; 16],
+    depth: u32,
+    weights: &HeuristicWeights,
+    rng: &mut impl Rng,
+) -> f64 {
+    if depth == 0 {
+        return evaluate(board, weights);
+    }
+    let empties: Vec<usize> = (0..16).filter(|&i| board[i].is_zero()).collect();
+    if empties.is_empty() {
+        return max_value(board, depth - 1, weights, rng);
+    }
+    let sampled: Vec<usize> = if empties.len() > CHANCE_SAMPLE_THRESHOLD {
+        empties
+            .choose_multiple(rng, CHANCE_SAMPLE_THRESHOLD)
+            .copied()
+            .collect()
+    } else {
+        empties
+    };
+    let cell_prob = 1.0 / sampled.len() as f64;
+    sampled
+        .iter()
+        .map(|&idx| {
+            let mut spawn_two = *board;
+            spawn_two[idx] = T::one();
+            let mut spawn_four = *board;
+            spawn_four[idx] = T::one() + T::one();
+            cell_prob
+                * 0.5
+                * (max_value(&spawn_two, depth - 1, weights, rng)
+                    + max_value(&spawn_four, depth - 1, weights, rng))
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn afterstate_returns_none_for_an_illegal_move() {
+        // Already packed leftmost with no equal neighbours, so sliding
+        // left again changes nothing.
+        let state: [u8; 16] = [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(afterstate(&state, &Action::Left), None);
+    }
+
+    #[test]
+    fn afterstate_merges_equal_neighbours_left() {
+        let state: [u8; 16] = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let after = afterstate(&state, &Action::Left).unwrap();
+        assert_eq!(after[0], 2);
+        assert_eq!(after[1], 0);
+    }
+
+    #[test]
+    fn best_action_returns_none_on_a_full_unmovable_board() {
+        // A checkerboard has no empty cells and no equal neighbours in any
+        // direction, so every action is illegal.
+        #[rustfmt::skip]
+        let state: [u8; 16] = [
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+        ];
+        assert_eq!(best_action(&state, 2), None);
+    }
+}